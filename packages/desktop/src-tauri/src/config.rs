@@ -0,0 +1,118 @@
+use std::{collections::HashMap, env, fs, path::PathBuf, time::Duration};
+
+use tauri::{AppHandle, path::BaseDirectory};
+
+const CONFIG_PATH: &str = "desktop/config.toml";
+
+const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_HEALTH_RETRIES: usize = 30;
+const DEFAULT_HEALTH_BASE_BACKOFF_MS: u64 = 100;
+const DEFAULT_HEALTH_REQUEST_TIMEOUT_MS: u64 = 2_000;
+
+/// `desktop/config.toml` as written on disk; every field is optional so a
+/// partial file only overrides what it mentions.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+struct ConfigFile {
+    host: Option<String>,
+    port_range_start: Option<u16>,
+    port_range_end: Option<u16>,
+    health_retries: Option<usize>,
+    health_base_backoff_ms: Option<u64>,
+    health_request_timeout_ms: Option<u64>,
+    extra_args: Vec<String>,
+    extra_env: HashMap<String, String>,
+}
+
+/// Resolved sidecar launch configuration: built-in defaults, overridden by
+/// `desktop/config.toml`, overridden in turn by environment variables.
+#[derive(Debug, Clone)]
+pub struct SidecarConfig {
+    pub host: String,
+    /// Ports `find_available_port` should try before falling back to an
+    /// ephemeral one.
+    pub port_range: Option<(u16, u16)>,
+    pub health_retries: usize,
+    pub health_base_backoff_ms: u64,
+    pub health_request_timeout: Duration,
+    pub extra_args: Vec<String>,
+    pub extra_env: HashMap<String, String>,
+}
+
+impl Default for SidecarConfig {
+    fn default() -> Self {
+        Self {
+            host: DEFAULT_HOST.to_string(),
+            port_range: None,
+            health_retries: DEFAULT_HEALTH_RETRIES,
+            health_base_backoff_ms: DEFAULT_HEALTH_BASE_BACKOFF_MS,
+            health_request_timeout: Duration::from_millis(DEFAULT_HEALTH_REQUEST_TIMEOUT_MS),
+            extra_args: Vec::new(),
+            extra_env: HashMap::new(),
+        }
+    }
+}
+
+fn config_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .resolve(CONFIG_PATH, BaseDirectory::AppLocalData)
+        .map_err(|e| format!("failed to resolve config file path: {e}"))
+}
+
+fn read_config_file(app: &AppHandle) -> Result<ConfigFile, String> {
+    let path = config_file_path(app)?;
+    if !path.exists() {
+        return Ok(ConfigFile::default());
+    }
+
+    let contents =
+        fs::read_to_string(&path).map_err(|e| format!("failed to read config file: {e}"))?;
+    toml::from_str(&contents).map_err(|e| format!("failed to parse config file: {e}"))
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+fn env_var_parsed<T: std::str::FromStr>(name: &str) -> Option<T> {
+    env_var(name).and_then(|value| value.parse().ok())
+}
+
+/// Loads the sidecar launch config, layering built-in defaults under
+/// `desktop/config.toml` under environment variables, in that priority
+/// order (env wins, then file, then default).
+pub fn load(app: &AppHandle) -> Result<SidecarConfig, String> {
+    let defaults = SidecarConfig::default();
+    let file = read_config_file(app)?;
+
+    let port_range = match (file.port_range_start, file.port_range_end) {
+        (Some(start), Some(end)) if start <= end => Some((start, end)),
+        _ => defaults.port_range,
+    };
+    let port_range = match (
+        env_var_parsed::<u16>("TREQ_PORT_RANGE_START"),
+        env_var_parsed::<u16>("TREQ_PORT_RANGE_END"),
+    ) {
+        (Some(start), Some(end)) if start <= end => Some((start, end)),
+        _ => port_range,
+    };
+
+    Ok(SidecarConfig {
+        host: env_var("TREQ_HOST")
+            .or(file.host)
+            .unwrap_or(defaults.host),
+        port_range,
+        health_retries: env_var_parsed("TREQ_HEALTH_RETRIES")
+            .or(file.health_retries)
+            .unwrap_or(defaults.health_retries),
+        health_base_backoff_ms: env_var_parsed("TREQ_HEALTH_BASE_BACKOFF_MS")
+            .or(file.health_base_backoff_ms)
+            .unwrap_or(defaults.health_base_backoff_ms),
+        health_request_timeout: env_var_parsed("TREQ_HEALTH_REQUEST_TIMEOUT_MS")
+            .or(file.health_request_timeout_ms)
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.health_request_timeout),
+        extra_args: file.extra_args,
+        extra_env: file.extra_env,
+    })
+}
@@ -0,0 +1,189 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use futures_util::{SinkExt, StreamExt};
+use tauri::{AppHandle, Emitter, async_runtime::JoinHandle};
+use tokio_tungstenite::tungstenite::{Message, http::Request};
+
+use crate::{
+    log::{self, LogStream},
+    sidecar::UnexpectedExitHook,
+};
+
+const EVENTS_PATH: &str = "/events";
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(10);
+const BASE_RECONNECT_BACKOFF_MS: u64 = 250;
+const MAX_RECONNECT_BACKOFF_MS: u64 = 10_000;
+/// Reconnect attempts in a row before the socket is treated as unrecoverable
+/// and the runtime is handed off to the supervisor.
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+const EVENT_SIDECAR_EVENT: &str = "sidecar-event";
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SidecarEventPayload {
+    workspace: String,
+    event: serde_json::Value,
+}
+
+/// Handle to a workspace's persistent `/events` connection. Stopping it
+/// tears down the reconnect loop along with the socket; dropping it without
+/// `stop` does not abort the loop.
+pub struct EventsConnection {
+    task: JoinHandle<()>,
+}
+
+impl EventsConnection {
+    pub fn stop(self) {
+        self.task.abort();
+    }
+
+    /// Stands in for a real connection in tests exercising `ServerState`.
+    #[cfg(test)]
+    pub(crate) fn noop() -> Self {
+        EventsConnection {
+            task: tauri::async_runtime::spawn(async {}),
+        }
+    }
+}
+
+/// Opens the sidecar's push-event channel and keeps it alive, reconnecting
+/// with backoff on drops. After `MAX_CONSECUTIVE_FAILURES` failed reconnects
+/// it calls `on_unhealthy`, handing the workspace off to the supervisor the
+/// same way an unexpected process exit would.
+pub fn connect(
+    app: AppHandle,
+    workspace_label: String,
+    base_url: String,
+    token: String,
+    shutting_down: Arc<AtomicBool>,
+    on_unhealthy: UnexpectedExitHook,
+) -> EventsConnection {
+    let task = tauri::async_runtime::spawn(async move {
+        let mut consecutive_failures = 0_u32;
+
+        while !shutting_down.load(Ordering::SeqCst) {
+            match run_connection(&app, &workspace_label, &base_url, &token, &shutting_down).await {
+                Ok(()) => consecutive_failures = 0,
+                Err(error) => {
+                    consecutive_failures += 1;
+                    log::emit_log_line(
+                        &app,
+                        &workspace_label,
+                        LogStream::Stderr,
+                        &format!("event channel dropped: {error}"),
+                    );
+                }
+            }
+
+            if shutting_down.load(Ordering::SeqCst) {
+                break;
+            }
+
+            if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+                log::emit_log_line(
+                    &app,
+                    &workspace_label,
+                    LogStream::Stderr,
+                    "event channel failed too many times in a row; handing off to supervisor",
+                );
+                on_unhealthy(None);
+                break;
+            }
+
+            tokio::time::sleep(reconnect_backoff(consecutive_failures)).await;
+        }
+    });
+
+    EventsConnection { task }
+}
+
+fn reconnect_backoff(attempt: u32) -> Duration {
+    let exponential = BASE_RECONNECT_BACKOFF_MS.saturating_mul(1_u64 << attempt.min(16));
+    Duration::from_millis(exponential.min(MAX_RECONNECT_BACKOFF_MS))
+}
+
+/// Runs a single connection attempt to completion: connect, then forward
+/// frames and answer the heartbeat until the socket drops or goes quiet.
+async fn run_connection(
+    app: &AppHandle,
+    workspace_label: &str,
+    base_url: &str,
+    token: &str,
+    shutting_down: &Arc<AtomicBool>,
+) -> Result<(), String> {
+    let ws_url = format!("{}{EVENTS_PATH}", base_url.replacen("http", "ws", 1));
+    let request = Request::builder()
+        .uri(&ws_url)
+        .header("Authorization", format!("Bearer {token}"))
+        .body(())
+        .map_err(|e| format!("failed to build events request: {e}"))?;
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| format!("failed to connect to {ws_url}: {e}"))?;
+
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    heartbeat.tick().await;
+
+    // Set only while a ping is outstanding, to the instant by which its pong
+    // (or any other frame proving the socket is alive) must arrive. Flat
+    // per-iteration receive timeouts would misfire on ordinary idle periods
+    // longer than `HEARTBEAT_TIMEOUT`, since the sidecar only pushes frames
+    // on events, not on a fixed schedule.
+    let mut pong_deadline: Option<tokio::time::Instant> = None;
+
+    loop {
+        if shutting_down.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        tokio::select! {
+            _ = heartbeat.tick(), if pong_deadline.is_none() => {
+                socket
+                    .send(Message::Ping(Vec::new()))
+                    .await
+                    .map_err(|e| format!("failed to send heartbeat ping: {e}"))?;
+                pong_deadline = Some(tokio::time::Instant::now() + HEARTBEAT_TIMEOUT);
+            }
+            _ = tokio::time::sleep_until(pong_deadline.unwrap_or_else(far_future)), if pong_deadline.is_some() => {
+                return Err("heartbeat timed out waiting for a pong".to_string());
+            }
+            frame = socket.next() => {
+                // Any frame, pong or otherwise, proves the socket is alive.
+                pong_deadline = None;
+                match frame {
+                    Some(Ok(Message::Text(text))) => forward_event(app, workspace_label, &text),
+                    Some(Ok(Message::Close(_))) | None => return Ok(()),
+                    Some(Err(error)) => return Err(format!("events socket error: {error}")),
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
+
+/// A timestamp far enough in the future to never fire; used as the disabled
+/// value for `pong_deadline` so the `select!` branch type-checks even when
+/// no ping is outstanding (the `if pong_deadline.is_some()` guard keeps it
+/// from ever actually being polled in that case).
+fn far_future() -> tokio::time::Instant {
+    tokio::time::Instant::now() + Duration::from_secs(365 * 24 * 60 * 60)
+}
+
+fn forward_event(app: &AppHandle, workspace_label: &str, raw: &str) {
+    let event =
+        serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()));
+    let payload = SidecarEventPayload {
+        workspace: workspace_label.to_string(),
+        event,
+    };
+    let _ = app.emit(EVENT_SIDECAR_EVENT, payload);
+}
@@ -0,0 +1,34 @@
+use keyring::Entry;
+use russh_keys::key::PublicKey;
+
+/// Keyring service name under which accepted SSH host key fingerprints are
+/// filed, keyed by `user@host:port` — the same trust-on-first-use contract
+/// as a traditional `~/.ssh/known_hosts`, just stored as OS-keyring entries
+/// instead of a flat file, mirroring how `token_store` files sidecar tokens.
+const SERVICE_NAME: &str = "dev.t-req.desktop.ssh-known-hosts";
+
+fn entry(host_id: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE_NAME, host_id)
+        .map_err(|e| format!("failed to open keyring entry: {e}"))
+}
+
+/// Trust-on-first-use: the first time a host key is seen for `host_id`, its
+/// fingerprint is remembered; every later connection must present the same
+/// fingerprint or `connect` fails instead of silently accepting whatever key
+/// the server happens to present, which would otherwise leave the tunnel
+/// open to a trivial MITM.
+pub fn verify(host_id: &str, server_key: &PublicKey) -> Result<bool, String> {
+    let entry = entry(host_id)?;
+    let seen_fingerprint = server_key.fingerprint();
+
+    match entry.get_password() {
+        Ok(known_fingerprint) => Ok(known_fingerprint == seen_fingerprint),
+        Err(keyring::Error::NoEntry) => {
+            entry
+                .set_password(&seen_fingerprint)
+                .map_err(|e| format!("failed to remember ssh host key: {e}"))?;
+            Ok(true)
+        }
+        Err(e) => Err(format!("failed to read known ssh host key: {e}")),
+    }
+}
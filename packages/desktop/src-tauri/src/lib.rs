@@ -1,19 +1,29 @@
+mod config;
+mod events;
+mod known_hosts;
+mod log;
 mod server;
 mod sidecar;
+mod ssh;
 mod state;
+mod supervisor;
+mod token_store;
 
 use std::{
     fs,
     path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, atomic::AtomicBool},
 };
 
+use log::{LogBuffers, SidecarLogLine};
 use server::check_health;
-use sidecar::{find_available_port, generate_token, spawn_sidecar};
+use sidecar::{SpawnTarget, UnexpectedExitHook, find_available_port, generate_token, spawn_sidecar};
 use state::{ServerInfo, ServerRuntime, ServerState};
+use supervisor::Supervisor;
 use tauri::{AppHandle, Emitter, Manager, RunEvent, State, path::BaseDirectory};
 use tauri_plugin_dialog::DialogExt;
 
-const SIDECAR_HOST: &str = "127.0.0.1";
 const EVENT_SERVER_READY: &str = "server-ready";
 const EVENT_SERVER_ERROR: &str = "server-error";
 const EVENT_WORKSPACE_PICKING: &str = "workspace-picking";
@@ -22,6 +32,7 @@ const WORKSPACE_STATE_PATH: &str = "desktop/workspace-state.json";
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ServerErrorPayload {
+    workspace: Option<String>,
     message: String,
 }
 
@@ -81,29 +92,128 @@ fn greet(name: &str) -> String {
 }
 
 #[tauri::command]
-fn get_server_info(state: State<'_, ServerState>) -> Result<Option<ServerInfo>, String> {
-    state.server_info()
+fn get_server_info(
+    state: State<'_, ServerState>,
+    workspace: String,
+) -> Result<Option<ServerInfo>, String> {
+    let workspace_path = resolve_workspace_path(&workspace)?;
+    state.server_info(&workspace_path)
+}
+
+#[tauri::command]
+fn list_servers(state: State<'_, ServerState>) -> Result<Vec<ServerInfo>, String> {
+    state.list_servers()
+}
+
+#[tauri::command]
+fn get_sidecar_logs(
+    buffers: State<'_, LogBuffers>,
+    workspace: String,
+) -> Result<Vec<SidecarLogLine>, String> {
+    buffers.tail(&workspace)
+}
+
+#[tauri::command]
+fn kill_sidecar(
+    state: State<'_, ServerState>,
+    workspace: String,
+    purge_token: Option<bool>,
+) -> Result<(), String> {
+    let workspace_path = resolve_workspace_path(&workspace)?;
+    state.kill_workspace(&workspace_path)?;
+
+    if purge_token.unwrap_or(false) {
+        token_store::purge_token(&workspace_path.to_string_lossy())?;
+    }
+
+    Ok(())
+}
+
+/// `get_server_info`/`kill_sidecar`'s counterparts for SSH-backed workspaces:
+/// the registry key there is the synthetic `ssh://user@host:port/path` string
+/// from `remote_workspace_key`, not a local path `resolve_workspace_path`
+/// would accept, so those commands can never address one.
+#[tauri::command]
+fn get_remote_server_info(
+    state: State<'_, ServerState>,
+    workspace: String,
+    host: String,
+    port: u16,
+    user: String,
+) -> Result<Option<ServerInfo>, String> {
+    let workspace_key = remote_workspace_key(&host, port, &user, &workspace);
+    state.server_info(&workspace_key)
 }
 
 #[tauri::command]
-fn kill_sidecar(state: State<'_, ServerState>) -> Result<(), String> {
-    state.kill_current()
+fn kill_remote_sidecar(
+    state: State<'_, ServerState>,
+    workspace: String,
+    host: String,
+    port: u16,
+    user: String,
+    purge_token: Option<bool>,
+) -> Result<(), String> {
+    let workspace_key = remote_workspace_key(&host, port, &user, &workspace);
+    state.kill_workspace(&workspace_key)?;
+
+    if purge_token.unwrap_or(false) {
+        token_store::purge_token(&workspace_key.to_string_lossy())?;
+    }
+
+    Ok(())
 }
 
 #[tauri::command]
 async fn set_workspace(app: AppHandle, workspace: String) -> Result<ServerInfo, String> {
     let workspace_path = resolve_workspace_path(&workspace)?;
     persist_last_workspace_path(&app, &workspace_path)?;
+    let workspace_label = workspace_path.to_string_lossy().to_string();
+
+    let result = initialize_server(
+        &app,
+        workspace_path.clone(),
+        workspace_label,
+        SpawnTarget::Local,
+    )
+    .await;
+
+    match result {
+        Ok(info) => Ok(info),
+        Err(error) => {
+            let workspace_display = workspace_path.to_string_lossy();
+            let _ = emit_server_error(&app, Some(&workspace_display), &error);
+            Err(error)
+        }
+    }
+}
 
-    match initialize_server(&app, workspace_path).await {
+/// Opens a workspace that lives on a remote host, tunnelling the sidecar's
+/// port back over SSH instead of spawning it as a local process.
+#[tauri::command]
+async fn set_remote_workspace(
+    app: AppHandle,
+    workspace: String,
+    host: String,
+    port: u16,
+    user: String,
+) -> Result<ServerInfo, String> {
+    let workspace_key = remote_workspace_key(&host, port, &user, &workspace);
+    let target = SpawnTarget::Ssh { host, port, user };
+
+    match initialize_server(&app, workspace_key, workspace.clone(), target).await {
         Ok(info) => Ok(info),
         Err(error) => {
-            let _ = emit_server_error(&app, &error);
+            let _ = emit_server_error(&app, Some(&workspace), &error);
             Err(error)
         }
     }
 }
 
+fn remote_workspace_key(host: &str, port: u16, user: &str, workspace: &str) -> PathBuf {
+    PathBuf::from(format!("ssh://{user}@{host}:{port}{workspace}"))
+}
+
 fn resolve_workspace_path(raw_path: &str) -> Result<PathBuf, String> {
     let workspace_path = PathBuf::from(raw_path);
     if !workspace_path.is_absolute() {
@@ -120,7 +230,8 @@ fn resolve_workspace_path(raw_path: &str) -> Result<PathBuf, String> {
     fs::read_dir(&workspace_path)
         .map_err(|e| format!("workspace directory is not readable: {e}"))?;
 
-    Ok(workspace_path)
+    fs::canonicalize(&workspace_path)
+        .map_err(|e| format!("failed to canonicalize workspace path: {e}"))
 }
 
 fn workspace_state_file_path(app: &AppHandle) -> Result<PathBuf, String> {
@@ -172,8 +283,13 @@ fn emit_server_ready(app: &AppHandle, info: &ServerInfo) -> Result<(), String> {
         .map_err(|e| format!("failed to emit server-ready event: {e}"))
 }
 
-fn emit_server_error(app: &AppHandle, message: &str) -> Result<(), String> {
+fn emit_server_error(
+    app: &AppHandle,
+    workspace: Option<&str>,
+    message: &str,
+) -> Result<(), String> {
     let payload = ServerErrorPayload {
+        workspace: workspace.map(str::to_string),
         message: message.to_string(),
     };
     app.emit(EVENT_SERVER_ERROR, payload)
@@ -226,36 +342,160 @@ async fn resolve_workspace_for_startup(app: &AppHandle) -> Result<PathBuf, Strin
     Ok(selected_path)
 }
 
-async fn initialize_server(app: &AppHandle, workspace_path: PathBuf) -> Result<ServerInfo, String> {
+/// Returns the already-running server for a workspace if it's still healthy,
+/// so switching back to a workspace doesn't needlessly respawn its sidecar.
+async fn reuse_healthy_runtime(
+    state: &ServerState,
+    workspace_key: &Path,
+    config: &config::SidecarConfig,
+) -> Option<ServerInfo> {
+    let info = state.server_info(workspace_key).ok().flatten()?;
+    check_health(&info.base_url, &info.token, config).await.ok()?;
+    Some(info)
+}
+
+/// Builds the closure the supervisor calls to bring a crashed workspace's
+/// sidecar back up on a fresh port, reusing the normal startup path (and so
+/// the workspace's stored token).
+///
+/// Always forces a real respawn rather than reusing the existing process: an
+/// HTTP health check alone can't tell the supervisor apart from the events
+/// socket having died while the process stayed up, and reusing in that case
+/// would leave the workspace without a push-event channel forever.
+fn build_respawn(app: AppHandle) -> supervisor::RespawnFn {
+    Arc::new(move |workspace_key: PathBuf, workspace_label: String, target: SpawnTarget| {
+        let app = app.clone();
+        Box::pin(async move {
+            initialize_server_inner(&app, workspace_key, workspace_label, target, true)
+                .await
+                .map(|_| ())
+        }) as Pin<Box<dyn std::future::Future<Output = Result<(), String>> + Send>>
+    })
+}
+
+fn build_unexpected_exit_hook(
+    app: AppHandle,
+    workspace_key: PathBuf,
+    workspace_label: String,
+    target: SpawnTarget,
+) -> UnexpectedExitHook {
+    Arc::new(move |code| {
+        supervisor::handle_unexpected_exit(
+            app.clone(),
+            workspace_key.clone(),
+            workspace_label.clone(),
+            target.clone(),
+            code,
+            build_respawn(app.clone()),
+        );
+    })
+}
+
+async fn initialize_server(
+    app: &AppHandle,
+    workspace_key: PathBuf,
+    workspace_label: String,
+    target: SpawnTarget,
+) -> Result<ServerInfo, String> {
+    initialize_server_inner(app, workspace_key, workspace_label, target, false).await
+}
+
+/// `force_respawn` skips the healthy-runtime reuse check, always tearing
+/// down and relaunching the sidecar. The supervisor hand-off path
+/// (`build_respawn`) needs this: an unhealthy events socket alone doesn't
+/// fail the HTTP health check `reuse_healthy_runtime` relies on, so without
+/// forcing a respawn the hand-off would just hand the same broken events
+/// connection straight back.
+async fn initialize_server_inner(
+    app: &AppHandle,
+    workspace_key: PathBuf,
+    workspace_label: String,
+    target: SpawnTarget,
+    force_respawn: bool,
+) -> Result<ServerInfo, String> {
+    let config = config::load(app)?;
+
     let state = app.state::<ServerState>();
-    state.kill_current()?;
+    if !force_respawn {
+        if let Some(info) = reuse_healthy_runtime(&state, &workspace_key, &config).await {
+            return Ok(info);
+        }
+    }
+    state.kill_workspace(&workspace_key)?;
     drop(state);
 
-    let port = find_available_port()?;
-    let token = generate_token();
-    let base_url = format!("http://{SIDECAR_HOST}:{port}");
-    let child = spawn_sidecar(app, port, &token, &workspace_path)?;
-
-    if let Err(error) = check_health(&base_url, &token).await {
-        let _ = child.kill();
+    let port = find_available_port(&config.host, config.port_range)?;
+    let workspace_key_str = workspace_key.to_string_lossy().to_string();
+    // Reuse a previously issued token when one is on file so restarting the
+    // sidecar doesn't invalidate clients that already hold it.
+    let token = token_store::load_token(&workspace_key_str).unwrap_or_else(generate_token);
+    token_store::store_token(&workspace_key_str, &token)?;
+    let base_url = format!("http://{}:{port}", config.host);
+    let shutting_down = Arc::new(AtomicBool::new(false));
+    let on_unexpected_exit = build_unexpected_exit_hook(
+        app.clone(),
+        workspace_key.clone(),
+        workspace_label.clone(),
+        target.clone(),
+    );
+    let process = spawn_sidecar(
+        app,
+        port,
+        &token,
+        Path::new(&workspace_label),
+        &target,
+        &config,
+        shutting_down.clone(),
+        on_unexpected_exit,
+    )
+    .await?;
+
+    if let Err(error) = check_health(&base_url, &token, &config).await {
+        let _ = process.kill();
         return Err(format!("sidecar failed health check: {error}"));
     }
+    // Marks the reference point the supervisor's stability-window reset
+    // measures from, so a crash loop isn't mistaken for recovered uptime
+    // just because its backoff sleep happened to run long.
+    app.state::<Supervisor>().mark_healthy(&workspace_key);
+
+    // Reuses the unexpected-exit hook's supervisor hand-off: a socket that
+    // can't be kept alive gets treated the same as a crashed process.
+    let on_unhealthy = build_unexpected_exit_hook(
+        app.clone(),
+        workspace_key.clone(),
+        workspace_label.clone(),
+        target,
+    );
+    let events = events::connect(
+        app.clone(),
+        workspace_label.clone(),
+        base_url.clone(),
+        token.clone(),
+        shutting_down.clone(),
+        on_unhealthy,
+    );
 
     let info = ServerInfo {
         port,
         token: token.clone(),
         base_url: base_url.clone(),
-        workspace: workspace_path.to_string_lossy().to_string(),
+        workspace: workspace_label,
     };
 
     let state = app.state::<ServerState>();
-    state.set_runtime(ServerRuntime {
-        child,
-        port,
-        token,
-        base_url,
-        workspace: info.workspace.clone(),
-    })?;
+    state.set_runtime(
+        workspace_key,
+        ServerRuntime {
+            process,
+            events,
+            port,
+            token,
+            base_url,
+            workspace: info.workspace.clone(),
+            shutting_down,
+        },
+    )?;
 
     emit_server_ready(app, &info)?;
     Ok(info)
@@ -271,7 +511,8 @@ async fn initialize_on_startup(app: &AppHandle) -> Result<(), String> {
 
     let init_result = async {
         let workspace_path = resolve_workspace_for_startup(app).await?;
-        initialize_server(app, workspace_path).await?;
+        let workspace_label = workspace_path.to_string_lossy().to_string();
+        initialize_server(app, workspace_path, workspace_label, SpawnTarget::Local).await?;
         Ok::<(), String>(())
     }
     .await;
@@ -290,17 +531,24 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .manage(ServerState::default())
         .manage(StartupState::default())
+        .manage(LogBuffers::default())
+        .manage(Supervisor::default())
         .invoke_handler(tauri::generate_handler![
             greet,
             get_server_info,
+            get_remote_server_info,
+            list_servers,
+            get_sidecar_logs,
             kill_sidecar,
-            set_workspace
+            kill_remote_sidecar,
+            set_workspace,
+            set_remote_workspace
         ])
         .setup(|app| {
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 if let Err(error) = initialize_on_startup(&app_handle).await {
-                    let _ = emit_server_error(&app_handle, &error);
+                    let _ = emit_server_error(&app_handle, None, &error);
                 }
             });
             Ok(())
@@ -310,7 +558,7 @@ pub fn run() {
         .run(|app, event| {
             if let RunEvent::Exit = event {
                 if let Some(state) = app.try_state::<ServerState>() {
-                    let _ = state.kill_current();
+                    let _ = state.kill_all();
                 }
             }
         });
@@ -0,0 +1,139 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, MutexGuard},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tauri::{AppHandle, Emitter, Manager};
+
+const EVENT_SIDECAR_LOG: &str = "sidecar-log";
+const EVENT_SIDECAR_TERMINATED: &str = "sidecar-terminated";
+
+/// How many log lines are kept per workspace for `get_sidecar_logs` to replay.
+const MAX_LINES_PER_WORKSPACE: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// Best-effort guess from the line's content; falls back to treating
+    /// stderr as warnings and stdout as plain info.
+    fn detect(line: &str, stream: LogStream) -> Self {
+        let lower = line.to_ascii_lowercase();
+        if lower.contains("error") {
+            LogLevel::Error
+        } else if lower.contains("warn") {
+            LogLevel::Warn
+        } else if lower.contains("debug") {
+            LogLevel::Debug
+        } else if lower.contains("trace") {
+            LogLevel::Trace
+        } else if stream == LogStream::Stderr {
+            LogLevel::Warn
+        } else {
+            LogLevel::Info
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarLogLine {
+    pub workspace: String,
+    pub stream: LogStream,
+    pub level: LogLevel,
+    pub timestamp_ms: u64,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SidecarTerminatedPayload {
+    workspace: String,
+    code: Option<i32>,
+}
+
+/// Bounded, per-workspace ring buffer backing `get_sidecar_logs`, so the UI
+/// can replay recent output even for logs emitted before it subscribed.
+#[derive(Default)]
+pub struct LogBuffers {
+    buffers: Mutex<HashMap<String, VecDeque<SidecarLogLine>>>,
+}
+
+impl LogBuffers {
+    fn record(&self, line: SidecarLogLine) -> Result<(), String> {
+        let mut guard = self.lock()?;
+        let buffer = guard.entry(line.workspace.clone()).or_default();
+        if buffer.len() == MAX_LINES_PER_WORKSPACE {
+            buffer.pop_front();
+        }
+        buffer.push_back(line);
+        Ok(())
+    }
+
+    pub fn tail(&self, workspace: &str) -> Result<Vec<SidecarLogLine>, String> {
+        let guard = self.lock()?;
+        Ok(guard
+            .get(workspace)
+            .map(|buffer| buffer.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<String, VecDeque<SidecarLogLine>>>, String> {
+        self.buffers
+            .lock()
+            .map_err(|_| "failed to acquire log buffer lock".to_string())
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Splits a raw stdout/stderr chunk into lines, tags each with workspace,
+/// stream and a best-effort level, records it, and forwards it to the UI.
+pub fn emit_log_line(app: &AppHandle, workspace: &str, stream: LogStream, raw: &str) {
+    let buffers = app.state::<LogBuffers>();
+
+    for line in raw.lines() {
+        if line.is_empty() {
+            continue;
+        }
+
+        let entry = SidecarLogLine {
+            workspace: workspace.to_string(),
+            stream,
+            level: LogLevel::detect(line, stream),
+            timestamp_ms: now_ms(),
+            line: line.to_string(),
+        };
+
+        let _ = buffers.record(entry.clone());
+        let _ = app.emit(EVENT_SIDECAR_LOG, entry);
+    }
+}
+
+pub fn emit_terminated(app: &AppHandle, workspace: &str, code: Option<i32>) {
+    let payload = SidecarTerminatedPayload {
+        workspace: workspace.to_string(),
+        code,
+    };
+    let _ = app.emit(EVENT_SIDECAR_TERMINATED, payload);
+}
@@ -1,13 +1,12 @@
 use std::time::Duration;
 
+use crate::config::SidecarConfig;
+
 const HEALTH_PATH: &str = "/health";
-const HEALTH_RETRIES: usize = 30;
-const HEALTH_BASE_BACKOFF_MS: u64 = 100;
-const HEALTH_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
 
-pub async fn check_health(base_url: &str, token: &str) -> Result<(), String> {
+pub async fn check_health(base_url: &str, token: &str, config: &SidecarConfig) -> Result<(), String> {
     let client = reqwest::Client::builder()
-        .timeout(HEALTH_REQUEST_TIMEOUT)
+        .timeout(config.health_request_timeout)
         .no_proxy()
         .build()
         .map_err(|e| format!("failed to construct health-check client: {e}"))?;
@@ -15,7 +14,7 @@ pub async fn check_health(base_url: &str, token: &str) -> Result<(), String> {
     let health_url = format!("{base_url}{HEALTH_PATH}");
     let mut last_error = "health check failed".to_string();
 
-    for attempt in 0..HEALTH_RETRIES {
+    for attempt in 0..config.health_retries {
         match client.get(&health_url).bearer_auth(token).send().await {
             Ok(response) if response.status().is_success() => return Ok(()),
             Ok(response) => {
@@ -30,8 +29,8 @@ pub async fn check_health(base_url: &str, token: &str) -> Result<(), String> {
             }
         }
 
-        if attempt + 1 < HEALTH_RETRIES {
-            let delay_ms = HEALTH_BASE_BACKOFF_MS * (attempt as u64 + 1);
+        if attempt + 1 < config.health_retries {
+            let delay_ms = config.health_base_backoff_ms * (attempt as u64 + 1);
             tokio::time::sleep(Duration::from_millis(delay_ms)).await;
         }
     }
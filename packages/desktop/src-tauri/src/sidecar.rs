@@ -1,6 +1,15 @@
-use std::{net::TcpListener, path::Path};
+use std::{
+    net::TcpListener,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
-use super::SIDECAR_HOST;
+use crate::config::SidecarConfig;
+use crate::log::{self, LogStream};
+use crate::ssh::SshSidecar;
 use rand::RngCore;
 use tauri::AppHandle;
 use tauri_plugin_shell::{
@@ -8,8 +17,58 @@ use tauri_plugin_shell::{
     process::{CommandChild, CommandEvent},
 };
 
-pub fn find_available_port() -> Result<u16, String> {
-    let listener = TcpListener::bind((SIDECAR_HOST, 0))
+/// Where a `treq` sidecar should run: on this machine, or on a remote host
+/// reached over SSH with the server port tunnelled back locally.
+#[derive(Debug, Clone)]
+pub enum SpawnTarget {
+    Local,
+    Ssh { host: String, port: u16, user: String },
+}
+
+/// Called with the process exit code when a sidecar terminates without the
+/// runtime having been intentionally torn down, so the supervisor can react.
+pub type UnexpectedExitHook = Arc<dyn Fn(Option<i32>) + Send + Sync>;
+
+/// A running sidecar, local or remote, that can be torn down uniformly.
+pub enum SidecarProcess {
+    Local(CommandChild),
+    Ssh(SshSidecar),
+    /// Stands in for a real process in tests exercising `ServerState`,
+    /// which otherwise has no cheap way to construct a `CommandChild`.
+    #[cfg(test)]
+    Noop,
+}
+
+impl SidecarProcess {
+    #[cfg(test)]
+    pub(crate) fn noop() -> Self {
+        SidecarProcess::Noop
+    }
+
+    pub fn kill(self) -> Result<(), String> {
+        match self {
+            SidecarProcess::Local(child) => child
+                .kill()
+                .map_err(|e| format!("failed to kill sidecar process: {e}")),
+            #[cfg(test)]
+            SidecarProcess::Noop => Ok(()),
+            SidecarProcess::Ssh(ssh) => ssh.kill(),
+        }
+    }
+}
+
+/// Tries each port in `preferred_range` first (e.g. to satisfy a firewall
+/// rule pinned to a range), falling back to an OS-assigned ephemeral port.
+pub fn find_available_port(host: &str, preferred_range: Option<(u16, u16)>) -> Result<u16, String> {
+    if let Some((start, end)) = preferred_range {
+        for port in start..=end {
+            if TcpListener::bind((host, port)).is_ok() {
+                return Ok(port);
+            }
+        }
+    }
+
+    let listener = TcpListener::bind((host, 0))
         .map_err(|e| format!("failed to bind ephemeral port: {e}"))?;
     let port = listener
         .local_addr()
@@ -29,48 +88,116 @@ pub fn generate_token() -> String {
         .collect::<String>()
 }
 
-pub fn spawn_sidecar(
+pub async fn spawn_sidecar(
     app: &AppHandle,
     port: u16,
     token: &str,
     workspace: &Path,
+    target: &SpawnTarget,
+    config: &SidecarConfig,
+    shutting_down: Arc<AtomicBool>,
+    on_unexpected_exit: UnexpectedExitHook,
+) -> Result<SidecarProcess, String> {
+    match target {
+        SpawnTarget::Local => spawn_local_sidecar(
+            app,
+            port,
+            token,
+            workspace,
+            config,
+            shutting_down,
+            on_unexpected_exit,
+        )
+        .map(SidecarProcess::Local),
+        SpawnTarget::Ssh { host, port: ssh_port, user } => {
+            let mut remote_args = vec![
+                "serve".to_string(),
+                "--host".to_string(),
+                config.host.clone(),
+                "--workspace".to_string(),
+                workspace.to_string_lossy().to_string(),
+            ];
+            remote_args.extend(config.extra_args.iter().cloned());
+            SshSidecar::connect(
+                app.clone(),
+                workspace.to_string_lossy().to_string(),
+                host,
+                *ssh_port,
+                user,
+                &config.host,
+                port,
+                token.to_string(),
+                remote_args,
+                config.extra_env.clone(),
+                shutting_down,
+                on_unexpected_exit,
+            )
+            .await
+            .map(SidecarProcess::Ssh)
+        }
+    }
+}
+
+fn spawn_local_sidecar(
+    app: &AppHandle,
+    port: u16,
+    token: &str,
+    workspace: &Path,
+    config: &SidecarConfig,
+    shutting_down: Arc<AtomicBool>,
+    on_unexpected_exit: UnexpectedExitHook,
 ) -> Result<CommandChild, String> {
     let workspace_path = workspace.to_string_lossy().to_string();
     let port_string = port.to_string();
-    let args = [
-        "serve",
-        "--host",
-        SIDECAR_HOST,
-        "--port",
-        port_string.as_str(),
-        "--token",
-        token,
-        "--workspace",
-        workspace_path.as_str(),
+    let mut args = vec![
+        "serve".to_string(),
+        "--host".to_string(),
+        config.host.clone(),
+        "--port".to_string(),
+        port_string,
+        "--workspace".to_string(),
+        workspace_path.clone(),
     ];
+    args.extend(config.extra_args.iter().cloned());
 
+    // Passed as an env var rather than `--token` on argv so the bearer token
+    // doesn't show up in `ps`/process-list output on this machine.
     let (mut events, child) = app
         .shell()
         .sidecar("treq")
         .map_err(|e| format!("failed to configure sidecar command: {e}"))?
         .args(args)
+        .env("TREQ_TOKEN", token)
+        .envs(config.extra_env.clone())
         .spawn()
         .map_err(|e| format!("failed to spawn treq sidecar: {e}"))?;
 
+    let app_for_events = app.clone();
+    let workspace_label = workspace_path;
     tauri::async_runtime::spawn(async move {
         while let Some(event) = events.recv().await {
             match event {
                 CommandEvent::Stdout(bytes) => {
-                    print!("{}", String::from_utf8_lossy(&bytes));
+                    let text = String::from_utf8_lossy(&bytes);
+                    log::emit_log_line(&app_for_events, &workspace_label, LogStream::Stdout, &text);
                 }
                 CommandEvent::Stderr(bytes) => {
-                    eprint!("{}", String::from_utf8_lossy(&bytes));
+                    let text = String::from_utf8_lossy(&bytes);
+                    log::emit_log_line(&app_for_events, &workspace_label, LogStream::Stderr, &text);
                 }
                 CommandEvent::Error(error) => {
-                    eprintln!("[sidecar] process error: {error}");
+                    log::emit_log_line(
+                        &app_for_events,
+                        &workspace_label,
+                        LogStream::Stderr,
+                        &format!("process error: {error}"),
+                    );
                 }
                 CommandEvent::Terminated(payload) => {
-                    eprintln!("[sidecar] process terminated: {:?}", payload.code);
+                    log::emit_terminated(&app_for_events, &workspace_label, payload.code);
+                    if !shutting_down.load(Ordering::SeqCst) {
+                        on_unexpected_exit(payload.code);
+                    }
                 }
                 _ => {}
             }
@@ -86,14 +213,22 @@ mod tests {
 
     #[test]
     fn finds_bindable_port() {
-        let port = find_available_port().expect("expected to resolve an available port");
+        let port =
+            find_available_port("127.0.0.1", None).expect("expected to resolve an available port");
         assert!(port > 0);
 
-        let probe = TcpListener::bind((SIDECAR_HOST, port))
+        let probe = TcpListener::bind(("127.0.0.1", port))
             .expect("expected returned port to be immediately bindable");
         drop(probe);
     }
 
+    #[test]
+    fn prefers_a_port_within_the_requested_range() {
+        let port = find_available_port("127.0.0.1", Some((20_500, 20_510)))
+            .expect("expected a port within range to be available");
+        assert!((20_500..=20_510).contains(&port));
+    }
+
     #[test]
     fn generates_32_byte_hex_token() {
         let token = generate_token();
@@ -0,0 +1,279 @@
+use std::collections::HashMap;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use crate::known_hosts;
+use crate::log::{self, LogStream};
+use crate::sidecar::UnexpectedExitHook;
+use rand::Rng;
+use russh::{ChannelMsg, client};
+use tauri::AppHandle;
+use tauri::async_runtime::JoinHandle;
+use tokio::net::TcpListener;
+
+/// Identifies this connection's host key in the `known_hosts` store.
+struct Handler {
+    host_id: String,
+}
+
+#[async_trait::async_trait]
+impl client::Handler for Handler {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &russh_keys::key::PublicKey,
+    ) -> Result<bool, Self::Error> {
+        // Trust-on-first-use against the keyring-backed known-hosts store:
+        // accepts and remembers a host key the first time it's seen, then
+        // requires an exact match on every later connection so a changed
+        // key (a possible MITM) gets rejected instead of silently trusted.
+        Ok(known_hosts::verify(&self.host_id, server_public_key).unwrap_or(false))
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+fn pick_remote_port() -> u16 {
+    rand::rngs::OsRng.gen_range(20_000..60_000)
+}
+
+/// An SSH connection running a remote `treq serve`, plus the local-forward
+/// listener tunnelling `<bind_host>:<local_port>` traffic to it.
+pub struct SshSidecar {
+    session: client::Handle<Handler>,
+    forward_task: JoinHandle<()>,
+    exec_task: JoinHandle<()>,
+}
+
+impl SshSidecar {
+    pub async fn connect(
+        app: AppHandle,
+        workspace_label: String,
+        host: &str,
+        ssh_port: u16,
+        user: &str,
+        bind_host: &str,
+        local_port: u16,
+        token: String,
+        mut remote_args: Vec<String>,
+        extra_env: HashMap<String, String>,
+        shutting_down: Arc<AtomicBool>,
+        on_unexpected_exit: UnexpectedExitHook,
+    ) -> Result<Self, String> {
+        let config = Arc::new(client::Config::default());
+        let handler = Handler {
+            host_id: format!("{user}@{host}:{ssh_port}"),
+        };
+        let mut session = client::connect(config, (host, ssh_port), handler)
+            .await
+            .map_err(|e| format!("failed to connect to {host}:{ssh_port}: {e}"))?;
+
+        let mut agent = russh_keys::agent::client::AgentClient::connect_env()
+            .await
+            .map_err(|e| format!("failed to reach ssh-agent: {e}"))?;
+        let identities = agent
+            .request_identities()
+            .await
+            .map_err(|e| format!("failed to list ssh-agent identities: {e}"))?;
+
+        let mut authenticated = false;
+        for identity in identities {
+            let (returned_agent, accepted) = session
+                .authenticate_future(user, identity, agent)
+                .await
+                .map_err(|e| format!("ssh authentication request failed: {e}"))?;
+            agent = returned_agent;
+            if accepted {
+                authenticated = true;
+                break;
+            }
+        }
+        if !authenticated {
+            return Err(format!(
+                "no ssh-agent identity was accepted for {user}@{host}"
+            ));
+        }
+
+        let remote_port = pick_remote_port();
+        remote_args.extend(["--port".to_string(), remote_port.to_string()]);
+        let exec_task = spawn_remote_exec(
+            &session,
+            app.clone(),
+            workspace_label.clone(),
+            token,
+            remote_args,
+            extra_env,
+            shutting_down,
+            on_unexpected_exit,
+        )
+        .await?;
+        let forward_task = spawn_local_forward(
+            session.clone(),
+            app,
+            workspace_label,
+            bind_host.to_string(),
+            local_port,
+            bind_host.to_string(),
+            remote_port,
+        )
+        .await?;
+
+        Ok(Self {
+            session,
+            forward_task,
+            exec_task,
+        })
+    }
+
+    pub fn kill(self) -> Result<(), String> {
+        self.forward_task.abort();
+        self.exec_task.abort();
+
+        let session = self.session;
+        tauri::async_runtime::spawn(async move {
+            let _ = session
+                .disconnect(russh::Disconnect::ByApplication, "treq sidecar stopped", "en")
+                .await;
+        });
+
+        Ok(())
+    }
+}
+
+async fn spawn_remote_exec(
+    session: &client::Handle<Handler>,
+    app: AppHandle,
+    workspace_label: String,
+    token: String,
+    command: Vec<String>,
+    extra_env: HashMap<String, String>,
+    shutting_down: Arc<AtomicBool>,
+    on_unexpected_exit: UnexpectedExitHook,
+) -> Result<JoinHandle<()>, String> {
+    let mut channel = session
+        .channel_open_session()
+        .await
+        .map_err(|e| format!("failed to open ssh exec channel: {e}"))?;
+
+    // Requested as channel env vars rather than baked into the command line
+    // so the bearer token doesn't show up in the remote host's process list.
+    // Most sshd configs reject env requests outside `AcceptEnv`, so this is
+    // best-effort; `treq serve` still needs to tolerate a missing token here.
+    let _ = channel.set_env(true, "TREQ_TOKEN", &token).await;
+    for (name, value) in &extra_env {
+        let _ = channel.set_env(true, name, value).await;
+    }
+
+    let command_line = command
+        .iter()
+        .map(|part| shell_quote(part))
+        .collect::<Vec<_>>()
+        .join(" ");
+    channel
+        .exec(true, command_line)
+        .await
+        .map_err(|e| format!("failed to start remote treq sidecar: {e}"))?;
+
+    Ok(tauri::async_runtime::spawn(async move {
+        while let Some(message) = channel.wait().await {
+            match message {
+                ChannelMsg::Data { data } => {
+                    let text = String::from_utf8_lossy(&data);
+                    log::emit_log_line(&app, &workspace_label, LogStream::Stdout, &text);
+                }
+                ChannelMsg::ExtendedData { data, .. } => {
+                    let text = String::from_utf8_lossy(&data);
+                    log::emit_log_line(&app, &workspace_label, LogStream::Stderr, &text);
+                }
+                ChannelMsg::ExitStatus { exit_status } => {
+                    let code = Some(exit_status as i32);
+                    log::emit_terminated(&app, &workspace_label, code);
+                    if !shutting_down.load(Ordering::SeqCst) {
+                        on_unexpected_exit(code);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }))
+}
+
+/// `remote_host` is dialed by the SSH server itself, not by us, so it must
+/// be the address the remote `treq serve` was told to bind (`config.host`,
+/// `127.0.0.1` by default) rather than the external SSH connection host.
+///
+/// `local_bind_host` must match that same `config.host`: `initialize_server`
+/// probes port availability and builds `base_url` against it, so binding
+/// the forward listener to a hardcoded `127.0.0.1` would leave it deaf to
+/// the address the rest of the app thinks the tunnel is listening on
+/// whenever the user configures a non-default host.
+async fn spawn_local_forward(
+    session: client::Handle<Handler>,
+    app: AppHandle,
+    workspace_label: String,
+    local_bind_host: String,
+    local_port: u16,
+    remote_host: String,
+    remote_port: u16,
+) -> Result<JoinHandle<()>, String> {
+    let listener = TcpListener::bind((local_bind_host.as_str(), local_port))
+        .await
+        .map_err(|e| format!("failed to bind local forward port {local_port}: {e}"))?;
+
+    Ok(tauri::async_runtime::spawn(async move {
+        loop {
+            let (local_stream, _) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(error) => {
+                    log::emit_log_line(
+                        &app,
+                        &workspace_label,
+                        LogStream::Stderr,
+                        &format!("[ssh-sidecar] forward listener error: {error}"),
+                    );
+                    continue;
+                }
+            };
+
+            let session = session.clone();
+            let remote_host = remote_host.clone();
+            let app = app.clone();
+            let workspace_label = workspace_label.clone();
+            tauri::async_runtime::spawn(async move {
+                let channel = match session
+                    .channel_open_direct_tcpip(&remote_host, remote_port as u32, "127.0.0.1", 0)
+                    .await
+                {
+                    Ok(channel) => channel,
+                    Err(error) => {
+                        log::emit_log_line(
+                            &app,
+                            &workspace_label,
+                            LogStream::Stderr,
+                            &format!("[ssh-sidecar] failed to open forward channel: {error}"),
+                        );
+                        return;
+                    }
+                };
+
+                let mut local_stream = local_stream;
+                let mut channel_stream = channel.into_stream();
+                if let Err(error) =
+                    tokio::io::copy_bidirectional(&mut local_stream, &mut channel_stream).await
+                {
+                    log::emit_log_line(
+                        &app,
+                        &workspace_label,
+                        LogStream::Stderr,
+                        &format!("[ssh-sidecar] forward connection closed: {error}"),
+                    );
+                }
+            });
+        }
+    }))
+}
@@ -1,6 +1,14 @@
-use std::sync::Mutex;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicBool, Ordering},
+    },
+};
 
-use tauri_plugin_shell::process::CommandChild;
+use crate::events::EventsConnection;
+use crate::sidecar::SidecarProcess;
 
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -12,63 +20,167 @@ pub struct ServerInfo {
 }
 
 pub struct ServerRuntime {
-    pub child: CommandChild,
+    pub process: SidecarProcess,
+    pub events: EventsConnection,
     pub port: u16,
     pub token: String,
     pub base_url: String,
     pub workspace: String,
+    /// Set before the process is intentionally killed so its event loop can
+    /// tell a deliberate shutdown apart from a crash the supervisor should
+    /// react to.
+    pub shutting_down: Arc<AtomicBool>,
 }
 
+impl ServerRuntime {
+    fn info(&self) -> ServerInfo {
+        ServerInfo {
+            port: self.port,
+            token: self.token.clone(),
+            base_url: self.base_url.clone(),
+            workspace: self.workspace.clone(),
+        }
+    }
+}
+
+/// Registry of live sidecars, one per canonicalized workspace path, so
+/// opening another workspace doesn't tear down servers already running.
 #[derive(Default)]
 pub struct ServerState {
-    runtime: Mutex<Option<ServerRuntime>>,
+    runtimes: Mutex<HashMap<PathBuf, ServerRuntime>>,
 }
 
 impl ServerState {
-    pub fn server_info(&self) -> Result<Option<ServerInfo>, String> {
-        let guard = self
-            .runtime
-            .lock()
-            .map_err(|_| "failed to acquire server state lock".to_string())?;
-
-        Ok(guard.as_ref().map(|runtime| ServerInfo {
-            port: runtime.port,
-            token: runtime.token.clone(),
-            base_url: runtime.base_url.clone(),
-            workspace: runtime.workspace.clone(),
-        }))
+    pub fn server_info(&self, workspace: &Path) -> Result<Option<ServerInfo>, String> {
+        let guard = self.lock()?;
+        Ok(guard.get(workspace).map(ServerRuntime::info))
     }
 
-    pub fn set_runtime(&self, runtime: ServerRuntime) -> Result<(), String> {
-        let mut guard = self
-            .runtime
-            .lock()
-            .map_err(|_| "failed to acquire server state lock".to_string())?;
+    pub fn list_servers(&self) -> Result<Vec<ServerInfo>, String> {
+        let guard = self.lock()?;
+        Ok(guard.values().map(ServerRuntime::info).collect())
+    }
+
+    pub fn set_runtime(&self, workspace: PathBuf, runtime: ServerRuntime) -> Result<(), String> {
+        let mut guard = self.lock()?;
+        if let Some(previous) = guard.insert(workspace, runtime) {
+            previous.shutting_down.store(true, Ordering::SeqCst);
+            previous.events.stop();
+            let _ = previous.process.kill();
+        }
+        Ok(())
+    }
+
+    pub fn take_runtime(&self, workspace: &Path) -> Result<Option<ServerRuntime>, String> {
+        let mut guard = self.lock()?;
+        let runtime = guard.remove(workspace);
+        if let Some(runtime) = &runtime {
+            runtime.shutting_down.store(true, Ordering::SeqCst);
+        }
+        Ok(runtime)
+    }
 
-        if let Some(current) = guard.take() {
-            let _ = current.child.kill();
+    pub fn kill_workspace(&self, workspace: &Path) -> Result<(), String> {
+        if let Some(runtime) = self.take_runtime(workspace)? {
+            runtime.events.stop();
+            runtime.process.kill()?;
         }
 
-        *guard = Some(runtime);
         Ok(())
     }
 
-    pub fn take_runtime(&self) -> Result<Option<ServerRuntime>, String> {
-        let mut guard = self
-            .runtime
+    pub fn kill_all(&self) -> Result<(), String> {
+        let mut guard = self.lock()?;
+        for (_, runtime) in guard.drain() {
+            runtime.shutting_down.store(true, Ordering::SeqCst);
+            runtime.events.stop();
+            let _ = runtime.process.kill();
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<PathBuf, ServerRuntime>>, String> {
+        self.runtimes
             .lock()
-            .map_err(|_| "failed to acquire server state lock".to_string())?;
-        Ok(guard.take())
+            .map_err(|_| "failed to acquire server state lock".to_string())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    pub fn kill_current(&self) -> Result<(), String> {
-        if let Some(runtime) = self.take_runtime()? {
-            runtime
-                .child
-                .kill()
-                .map_err(|e| format!("failed to kill sidecar process: {e}"))?;
+    fn test_runtime(workspace: &str, port: u16) -> ServerRuntime {
+        ServerRuntime {
+            process: SidecarProcess::noop(),
+            events: EventsConnection::noop(),
+            port,
+            token: format!("token-{port}"),
+            base_url: format!("http://127.0.0.1:{port}"),
+            workspace: workspace.to_string(),
+            shutting_down: Arc::new(AtomicBool::new(false)),
         }
+    }
 
-        Ok(())
+    #[test]
+    fn list_servers_reflects_every_inserted_workspace() {
+        let state = ServerState::default();
+        state
+            .set_runtime(PathBuf::from("/a"), test_runtime("a", 4001))
+            .unwrap();
+        state
+            .set_runtime(PathBuf::from("/b"), test_runtime("b", 4002))
+            .unwrap();
+
+        let mut ports: Vec<u16> = state.list_servers().unwrap().iter().map(|i| i.port).collect();
+        ports.sort();
+        assert_eq!(ports, vec![4001, 4002]);
+    }
+
+    #[test]
+    fn set_runtime_for_an_existing_workspace_tears_down_the_previous_one() {
+        let state = ServerState::default();
+        let previous_shutting_down = Arc::new(AtomicBool::new(false));
+        let mut first = test_runtime("a", 4001);
+        first.shutting_down = previous_shutting_down.clone();
+
+        state.set_runtime(PathBuf::from("/a"), first).unwrap();
+        state
+            .set_runtime(PathBuf::from("/a"), test_runtime("a", 4002))
+            .unwrap();
+
+        assert!(previous_shutting_down.load(Ordering::SeqCst));
+        assert_eq!(state.server_info(Path::new("/a")).unwrap().unwrap().port, 4002);
+    }
+
+    #[test]
+    fn kill_workspace_removes_only_that_workspace() {
+        let state = ServerState::default();
+        state
+            .set_runtime(PathBuf::from("/a"), test_runtime("a", 4001))
+            .unwrap();
+        state
+            .set_runtime(PathBuf::from("/b"), test_runtime("b", 4002))
+            .unwrap();
+
+        state.kill_workspace(Path::new("/a")).unwrap();
+
+        assert!(state.server_info(Path::new("/a")).unwrap().is_none());
+        assert_eq!(state.server_info(Path::new("/b")).unwrap().unwrap().port, 4002);
+    }
+
+    #[test]
+    fn kill_all_clears_the_registry() {
+        let state = ServerState::default();
+        state
+            .set_runtime(PathBuf::from("/a"), test_runtime("a", 4001))
+            .unwrap();
+        state
+            .set_runtime(PathBuf::from("/b"), test_runtime("b", 4002))
+            .unwrap();
+
+        state.kill_all().unwrap();
+
+        assert!(state.list_servers().unwrap().is_empty());
     }
 }
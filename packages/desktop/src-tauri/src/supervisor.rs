@@ -0,0 +1,276 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    log::{self, LogStream},
+    sidecar::SpawnTarget,
+};
+
+const BASE_BACKOFF_MS: u64 = 500;
+const MAX_BACKOFF_MS: u64 = 30_000;
+/// How long a restarted sidecar must stay up before its crash history is
+/// forgotten and the backoff/attempt cap resets.
+const STABILITY_WINDOW: Duration = Duration::from_secs(30);
+/// How far back restarts count against the cap.
+const RESTART_WINDOW: Duration = Duration::from_secs(5 * 60);
+const MAX_RESTARTS_PER_WINDOW: u32 = 8;
+
+/// Respawns a workspace's sidecar on a fresh port, mirroring whatever
+/// `initialize_server` would do for a first launch.
+pub type RespawnFn = Arc<
+    dyn Fn(PathBuf, String, SpawnTarget) -> Pin<Box<dyn Future<Output = Result<(), String>> + Send>>
+        + Send
+        + Sync,
+>;
+
+#[derive(Default)]
+struct RestartState {
+    restarts: VecDeque<Instant>,
+    /// When the workspace's sidecar last passed its post-respawn health
+    /// check. Distinct from the instant of the most recent crash decision:
+    /// the backoff sleep between a crash and its respawn can itself exceed
+    /// `STABILITY_WINDOW`, so measuring stability from the crash timestamp
+    /// would mistake "we waited a while before retrying" for "it ran fine
+    /// for a while" — even for a sidecar that crashes instantly every time.
+    healthy_since: Option<Instant>,
+}
+
+/// Tracks crash-restart history per workspace so an unexpectedly terminated
+/// sidecar gets respawned with capped exponential backoff instead of either
+/// spinning forever or staying dead until the user re-picks the workspace.
+#[derive(Default)]
+pub struct Supervisor {
+    restarts: Mutex<HashMap<PathBuf, RestartState>>,
+}
+
+/// Schedules a backoff-delayed restart in the background; returns
+/// immediately so the caller (the sidecar's event loop) isn't blocked.
+pub fn handle_unexpected_exit(
+    app: AppHandle,
+    workspace_key: PathBuf,
+    workspace_label: String,
+    target: SpawnTarget,
+    exit_code: Option<i32>,
+    respawn: RespawnFn,
+) {
+    tauri::async_runtime::spawn(async move {
+        let supervisor = app.state::<Supervisor>();
+        supervisor
+            .supervise_restart(&app, workspace_key, workspace_label, target, exit_code, respawn)
+            .await;
+    });
+}
+
+impl Supervisor {
+    async fn supervise_restart(
+        &self,
+        app: &AppHandle,
+        workspace_key: PathBuf,
+        workspace_label: String,
+        target: SpawnTarget,
+        exit_code: Option<i32>,
+        respawn: RespawnFn,
+    ) {
+        log::emit_log_line(
+            app,
+            &workspace_label,
+            LogStream::Stderr,
+            &format!("sidecar exited unexpectedly (code {exit_code:?}); supervisor will restart it"),
+        );
+
+        let Some(attempt) = self.next_attempt(&workspace_key) else {
+            let _ = crate::emit_server_error(
+                app,
+                Some(&workspace_label),
+                "sidecar crashed repeatedly; giving up on automatic restarts",
+            );
+            return;
+        };
+
+        tokio::time::sleep(backoff_delay(attempt)).await;
+
+        if let Err(error) = respawn(workspace_key, workspace_label.clone(), target).await {
+            let _ = crate::emit_server_error(
+                app,
+                Some(&workspace_label),
+                &format!("automatic restart failed: {error}"),
+            );
+        }
+    }
+
+    /// Returns the attempt number to use for backoff, or `None` once the
+    /// workspace has hit the restart cap within the rolling window.
+    fn next_attempt(&self, key: &Path) -> Option<u32> {
+        self.next_attempt_at(key, Instant::now())
+    }
+
+    /// `next_attempt` with the current time taken as a parameter, so the
+    /// window/cap arithmetic can be exercised deterministically in tests
+    /// without sleeping for real `STABILITY_WINDOW`/`RESTART_WINDOW` spans.
+    fn next_attempt_at(&self, key: &Path, now: Instant) -> Option<u32> {
+        let mut guard = self
+            .restarts
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let state = guard.entry(key.to_path_buf()).or_default();
+
+        // A restart that actually passed its health check and then ran for
+        // the whole stability window means the crash loop has stopped, so
+        // earlier attempts no longer count against the cap. A restart that
+        // never got that far (e.g. it crashed again before anyone marked it
+        // healthy) must not reset anything, no matter how long the backoff
+        // sleep before this crash happened to be.
+        if matches!(state.healthy_since, Some(healthy_at) if now.duration_since(healthy_at) >= STABILITY_WINDOW)
+        {
+            state.restarts.clear();
+        }
+        state.healthy_since = None;
+
+        while matches!(state.restarts.front(), Some(first) if now.duration_since(*first) > RESTART_WINDOW)
+        {
+            state.restarts.pop_front();
+        }
+
+        if state.restarts.len() as u32 >= MAX_RESTARTS_PER_WINDOW {
+            return None;
+        }
+
+        state.restarts.push_back(now);
+        Some(state.restarts.len() as u32 - 1)
+    }
+
+    /// Records that the workspace's sidecar just passed its post-respawn
+    /// health check, as the reference point for the stability-window reset
+    /// in `next_attempt_at`. Called from the normal startup/respawn path,
+    /// not from the supervisor's own restart loop, since that's where the
+    /// health check actually happens.
+    pub fn mark_healthy(&self, key: &Path) {
+        self.mark_healthy_at(key, Instant::now());
+    }
+
+    fn mark_healthy_at(&self, key: &Path, at: Instant) {
+        let mut guard = self
+            .restarts
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        guard.entry(key.to_path_buf()).or_default().healthy_since = Some(at);
+    }
+}
+
+fn backoff_delay(attempt: u32) -> Duration {
+    let exponential = BASE_BACKOFF_MS.saturating_mul(1_u64 << attempt.min(20));
+    let capped_ms = exponential.min(MAX_BACKOFF_MS) as f64;
+    let jitter = rand::rngs::OsRng.gen_range(0.5..1.5_f64);
+    Duration::from_millis((capped_ms * jitter) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_delay_grows_then_caps_with_jitter() {
+        let small = backoff_delay(0).as_millis() as f64;
+        assert!(small >= BASE_BACKOFF_MS as f64 * 0.5 && small <= BASE_BACKOFF_MS as f64 * 1.5);
+
+        let capped = backoff_delay(20).as_millis() as f64;
+        assert!(capped >= MAX_BACKOFF_MS as f64 * 0.5 && capped <= MAX_BACKOFF_MS as f64 * 1.5);
+    }
+
+    #[test]
+    fn next_attempt_counts_up_then_hits_the_cap() {
+        let supervisor = Supervisor::default();
+        let key = Path::new("/workspace/a");
+        let now = Instant::now();
+
+        for expected in 0..MAX_RESTARTS_PER_WINDOW {
+            assert_eq!(supervisor.next_attempt_at(key, now), Some(expected));
+        }
+        assert_eq!(supervisor.next_attempt_at(key, now), None);
+    }
+
+    #[test]
+    fn next_attempt_is_tracked_independently_per_workspace() {
+        let supervisor = Supervisor::default();
+        let now = Instant::now();
+
+        assert_eq!(supervisor.next_attempt_at(Path::new("/workspace/a"), now), Some(0));
+        assert_eq!(supervisor.next_attempt_at(Path::new("/workspace/b"), now), Some(0));
+        assert_eq!(supervisor.next_attempt_at(Path::new("/workspace/a"), now), Some(1));
+    }
+
+    #[test]
+    fn next_attempt_frees_up_once_the_restart_history_ages_out() {
+        let supervisor = Supervisor::default();
+        let key = Path::new("/workspace/a");
+        let now = Instant::now();
+
+        for _ in 0..MAX_RESTARTS_PER_WINDOW {
+            supervisor.next_attempt_at(key, now);
+        }
+        assert_eq!(supervisor.next_attempt_at(key, now), None);
+
+        // Once the whole restart history is older than `RESTART_WINDOW`,
+        // the cap has room again.
+        let later = now + RESTART_WINDOW + Duration::from_secs(1);
+        assert_eq!(supervisor.next_attempt_at(key, later), Some(0));
+    }
+
+    #[test]
+    fn next_attempt_resets_once_a_respawn_is_marked_healthy_and_stays_up() {
+        let supervisor = Supervisor::default();
+        let key = Path::new("/workspace/a");
+        let now = Instant::now();
+
+        assert_eq!(supervisor.next_attempt_at(key, now), Some(0));
+        assert_eq!(supervisor.next_attempt_at(key, now), Some(1));
+
+        // The second restart passed its health check...
+        let healthy_at = now + Duration::from_secs(1);
+        supervisor.mark_healthy_at(key, healthy_at);
+
+        // ...and stayed up through the whole stability window, so the next
+        // crash starts the attempt counter back at 0.
+        let stable_later = healthy_at + STABILITY_WINDOW;
+        assert_eq!(supervisor.next_attempt_at(key, stable_later), Some(0));
+    }
+
+    #[test]
+    fn next_attempt_does_not_reset_on_an_immediate_crash_after_backoff() {
+        // Regression test: `MAX_BACKOFF_MS` and `STABILITY_WINDOW` are both
+        // 30s, so a sidecar stuck crashing instantly on every respawn can
+        // easily go `>= STABILITY_WINDOW` between crash decisions purely
+        // from sleeping out its own (capped, then jittered up to 1.5x) backoff -- with no
+        // health check ever passing in between. That must not reset the
+        // attempt counter, or `MAX_RESTARTS_PER_WINDOW` would never bind.
+        let supervisor = Supervisor::default();
+        let key = Path::new("/workspace/a");
+        let now = Instant::now();
+
+        // Drive the attempt count up to where `backoff_delay` is capped at
+        // `MAX_BACKOFF_MS`, same as the real crash loop this is modelling.
+        let mut last_decision = now;
+        let mut last_attempt = 0;
+        for _ in 0..7 {
+            last_attempt = supervisor.next_attempt_at(key, last_decision).unwrap();
+            last_decision += Duration::from_millis(1);
+        }
+
+        // The sidecar crashes again right after its backoff sleep elapses,
+        // without ever passing a health check in between.
+        let instant_recrash = last_decision + STABILITY_WINDOW;
+        assert_eq!(
+            supervisor.next_attempt_at(key, instant_recrash),
+            Some(last_attempt + 1)
+        );
+    }
+}
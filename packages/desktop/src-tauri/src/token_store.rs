@@ -0,0 +1,29 @@
+use keyring::Entry;
+
+/// Keyring service name under which per-workspace sidecar tokens are filed.
+const SERVICE_NAME: &str = "dev.t-req.desktop.sidecar-token";
+
+fn entry(workspace_key: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE_NAME, workspace_key)
+        .map_err(|e| format!("failed to open keyring entry: {e}"))
+}
+
+/// Returns the token previously stored for this workspace, if any. Missing
+/// entries (never stored, or purged) are treated as "no token" rather than
+/// an error so a fresh token can be generated in their place.
+pub fn load_token(workspace_key: &str) -> Option<String> {
+    entry(workspace_key).ok()?.get_password().ok()
+}
+
+pub fn store_token(workspace_key: &str, token: &str) -> Result<(), String> {
+    entry(workspace_key)?
+        .set_password(token)
+        .map_err(|e| format!("failed to persist sidecar token: {e}"))
+}
+
+pub fn purge_token(workspace_key: &str) -> Result<(), String> {
+    match entry(workspace_key)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("failed to purge sidecar token: {e}")),
+    }
+}